@@ -0,0 +1,64 @@
+//! `std::io` adapters for the guards, so they can be used as a drop-in
+//! sink/source for `Write`/`Read`-based encoders (`serde`, `bincode`, ...).
+//!
+//! This module pulls in `std` (see the crate-level `extern crate std`), so
+//! it's only built when the `io` feature is enabled; the `no_std` build is
+//! unaffected. A `no_std` caller that still wants these traits can back them
+//! with a `core_io`-style shim crate instead.
+
+use std::io::{self, BufRead, Read, Write};
+
+use super::{ReadGuard, WriteGuard};
+
+impl<'a, 'b> ReadGuard<'a, 'b> {
+    /// The current offset of the internal cursor into the guard's buffer.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'a, 'b> Read for ReadGuard<'a, 'b> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = &self.buffer[self.position..];
+        let len = remaining.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.position += len;
+
+        Ok(len)
+    }
+}
+
+impl<'a, 'b> BufRead for ReadGuard<'a, 'b> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(&self.buffer[self.position..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.position += amt;
+    }
+}
+
+impl<'a, 'b> WriteGuard<'a, 'b> {
+    /// Resets the internal cursor to the start of the guard's buffer, so it
+    /// can be reused like a `Cursor<&[u8]>`.
+    pub fn rewind(&mut self) {
+        self.position = 0;
+    }
+}
+
+impl<'a, 'b> Write for WriteGuard<'a, 'b> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let remaining = self.buffer.len() - self.position;
+        let len = remaining.min(buf.len());
+
+        self.buffer[self.position..self.position + len].copy_from_slice(&buf[..len]);
+        self.position += len;
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
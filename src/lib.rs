@@ -1,35 +1,76 @@
+#![no_std]
+
+#[cfg(all(feature = "async", feature = "blocking"))]
+compile_error!(
+    "the `async` and `blocking` features both define `Reader::lock`/`Writer::lock`; enable at most one of the two"
+);
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(any(feature = "io", feature = "std"))]
+extern crate std;
+
 mod toggle;
 
+#[cfg(feature = "io")]
+mod io;
+
+#[cfg(feature = "async")]
+mod waker;
+
+#[cfg(feature = "async")]
+use core::task::{Context, Poll};
+
 use toggle::{Side, State, ToggleCount};
 
-use std::ops::{Deref, DerefMut};
-use std::slice;
-use std::sync::atomic::AtomicUsize;
-use std::sync::atomic::Ordering;
-use std::sync::Mutex;
+use core::ops::{Deref, DerefMut};
+use core::slice;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
+
+#[cfg(feature = "alloc")]
+use core::sync::atomic::AtomicBool;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
+/// An owned, heap-allocated doublet.
+///
+/// This is the easiest way to get a doublet: it owns its own buffers and
+/// header, so there's no need to manage shared memory yourself. See
+/// [`Doublet::from_raw_parts`](struct.Reader.html#method.from_raw_parts) (via
+/// [`Reader`]/[`Writer`]) for the `no_std` alternative.
+#[cfg(feature = "alloc")]
 #[derive(Debug)]
 pub struct OwnedDoublet {
     header: Header,
 
+    #[cfg(feature = "async")]
+    async_state: waker::AsyncState,
+
     left_buffer: Vec<u8>,
     right_buffer: Vec<u8>,
 
-    has_writer: Mutex<bool>,
+    has_writer: AtomicBool,
 }
 
+#[cfg(feature = "alloc")]
 impl OwnedDoublet {
     pub fn new(size: usize) -> OwnedDoublet {
         Self {
             header: Header {
-            toggle: ToggleCount::default(),
-            remaining_readers: AtomicUsize::new(0),
+                toggle: ToggleCount::default(),
+                remaining_readers: AtomicUsize::new(0),
             },
 
-            left_buffer: vec![0; size],
-            right_buffer: vec![0; size],
+            #[cfg(feature = "async")]
+            async_state: waker::AsyncState::default(),
+
+            left_buffer: alloc::vec![0; size],
+            right_buffer: alloc::vec![0; size],
 
-            has_writer: Mutex::new(true),
+            has_writer: AtomicBool::new(true),
         }
     }
 
@@ -37,6 +78,9 @@ impl OwnedDoublet {
         Doublet {
             header: &self.header,
 
+            #[cfg(feature = "async")]
+            async_state: Some(&self.async_state),
+
             left_buffer: self.left_buffer.as_ptr() as *mut _,
             right_buffer: self.right_buffer.as_ptr() as *mut _,
 
@@ -45,15 +89,9 @@ impl OwnedDoublet {
     }
 
     pub fn take_writer(&self) -> Option<Writer> {
-        {
-            let mut has_writer = self.has_writer.lock().expect("lock writer mutex");
-
-            if !*has_writer {
-                return None;
-            }
-
-            *has_writer = false;
-        }
+        self.has_writer
+            .compare_exchange(true, false, Ordering::AcqRel, Ordering::Acquire)
+            .ok()?;
 
         let doublet = self.make_doublet();
 
@@ -68,10 +106,25 @@ impl OwnedDoublet {
     }
 }
 
+/// The total number of bytes a raw buffer must hold to fit a doublet with
+/// `buffer_size` bytes on each side, including the header.
+///
+/// This has no allocator dependency, so it's available even without the
+/// `alloc` feature: it's the sizing helper for a caller-provided region of
+/// static or shared memory used with [`Reader::from_raw_parts`] /
+/// [`Writer::from_raw_parts`].
 pub fn raw_size(buffer_size: usize) -> usize {
     Doublet::header_size() + (buffer_size * 2)
 }
 
+/// The state placed in a doublet's raw/shared memory region.
+///
+/// This is `repr(C)` and is exactly what [`Doublet::from_raw_parts`] maps
+/// onto caller-provided memory, which may be backed by a `static`, another
+/// process, or another core entirely. It must only ever contain types with
+/// no process-local identity (no pointers, no vtables) — see
+/// [`waker::AsyncState`]'s doc comment for why the `async` feature's waker
+/// storage is deliberately kept out of here instead.
 #[derive(Debug)]
 #[repr(C)]
 struct Header {
@@ -83,6 +136,9 @@ struct Header {
 struct Doublet<'a> {
     header: &'a Header,
 
+    #[cfg(feature = "async")]
+    async_state: Option<&'a waker::AsyncState>,
+
     size: usize,
     left_buffer: *mut u8,
     right_buffer: *mut u8,
@@ -105,7 +161,7 @@ impl<'a> Doublet<'a> {
     }
 
     fn header_size() -> usize {
-        std::mem::size_of::<Header>()
+        core::mem::size_of::<Header>()
     }
 
     unsafe fn from_raw_parts(buf: *mut u8, size: usize) -> Result<Self, ()> {
@@ -131,6 +187,14 @@ impl<'a> Doublet<'a> {
         Ok(Self {
             header: &*hdr_ptr,
 
+            // There's no process-local place to put waker registrations for
+            // memory that came in over `from_raw_parts` (it may not even be
+            // backed by this process' address space); `poll_lock` on these
+            // just attempts the lock without registering a waker, so the
+            // caller is responsible for re-polling on its own schedule.
+            #[cfg(feature = "async")]
+            async_state: None,
+
             size: buffer_size,
             left_buffer,
             right_buffer,
@@ -175,12 +239,68 @@ impl<'b> Reader<'b> {
             reader: self,
             reading_from: new.side,
             buffer,
+            #[cfg(feature = "io")]
+            position: 0,
         };
 
         Ok(guard)
     }
 }
 
+#[cfg(feature = "async")]
+impl<'b> Reader<'b> {
+    /// Polls for a read lock, registering `cx`'s waker before attempting
+    /// the lock.
+    ///
+    /// The registration happens first so that a write landing between it
+    /// and the attempt below still results in a wakeup instead of a lost
+    /// one. A `Reader` built from [`Reader::from_raw_parts`] has nowhere to
+    /// keep a waker registration (see [`waker::AsyncState`]), so it skips
+    /// registration and relies on the caller to re-poll.
+    pub fn poll_lock<'a>(&'a mut self, cx: &mut Context<'_>) -> Poll<ReadGuard<'a, 'b>> {
+        if let Some(async_state) = self.0.async_state {
+            async_state.reader_wakers.register(cx.waker());
+        }
+
+        match self.try_lock() {
+            Ok(guard) => Poll::Ready(guard),
+            Err(()) => Poll::Pending,
+        }
+    }
+
+    /// Waits for a read lock, suspending the calling task instead of
+    /// busy-polling.
+    ///
+    /// Mutually exclusive with the `blocking` feature's synchronous
+    /// `Reader::lock`; enable at most one of the two.
+    pub fn lock<'a>(&'a mut self) -> ReadLock<'a, 'b> {
+        ReadLock { reader: self }
+    }
+}
+
+/// The [`Future`](core::future::Future) returned by [`Reader::lock`].
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct ReadLock<'a, 'b> {
+    reader: &'a mut Reader<'b>,
+}
+
+#[cfg(feature = "async")]
+impl<'a, 'b> core::future::Future for ReadLock<'a, 'b> {
+    type Output = ReadGuard<'a, 'b>;
+
+    fn poll(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = core::pin::Pin::get_mut(self);
+
+        // SAFETY: `this.reader` is already a unique `&'a mut Reader<'b>`
+        // borrow owned by this future; the cast just recovers that `'a`
+        // from the shorter reborrow `get_mut` hands back on each poll.
+        let reader = unsafe { &mut *(this.reader as *mut Reader<'b>) };
+
+        reader.poll_lock(cx)
+    }
+}
+
 #[derive(Debug)]
 pub struct ReadGuard<'a, 'b>
 where
@@ -189,6 +309,9 @@ where
     reading_from: Side,
     reader: &'a mut Reader<'b>,
     buffer: &'b [u8],
+
+    #[cfg(feature = "io")]
+    position: usize,
 }
 
 impl<'a, 'b> Deref for ReadGuard<'a, 'b> {
@@ -222,10 +345,21 @@ impl<'a, 'b> Drop for ReadGuard<'a, 'b> {
             }
         }
 
-        self.reader
+        #[cfg_attr(not(feature = "async"), allow(unused_variables))]
+        let prev = self
+            .reader
             .0
             .remaining_readers()
             .fetch_sub(1, Ordering::SeqCst);
+
+        #[cfg(feature = "async")]
+        {
+            if prev == 1 {
+                if let Some(async_state) = self.reader.0.async_state {
+                    async_state.writer_waker.wake();
+                }
+            }
+        }
     }
 }
 
@@ -255,6 +389,8 @@ impl<'b> Writer<'b> {
                 buffer,
                 writing_to,
                 writer: Some(self),
+                #[cfg(feature = "io")]
+                position: 0,
             };
 
             Ok(guard)
@@ -264,6 +400,58 @@ impl<'b> Writer<'b> {
     }
 }
 
+#[cfg(feature = "async")]
+impl<'b> Writer<'b> {
+    /// Polls for a write lock, registering `cx`'s waker before attempting
+    /// the lock.
+    ///
+    /// The registration happens first so that a reader finishing between
+    /// it and the attempt below still results in a wakeup instead of a
+    /// lost one. A `Writer` built from [`Writer::from_raw_parts`] has
+    /// nowhere to keep a waker registration (see [`waker::AsyncState`]), so
+    /// it skips registration and relies on the caller to re-poll.
+    pub fn poll_lock<'a>(&'a mut self, cx: &mut Context<'_>) -> Poll<WriteGuard<'a, 'b>> {
+        if let Some(async_state) = self.0.async_state {
+            async_state.writer_waker.register(cx.waker());
+        }
+
+        match self.try_lock() {
+            Ok(guard) => Poll::Ready(guard),
+            Err(()) => Poll::Pending,
+        }
+    }
+
+    /// Waits for a write lock, suspending the calling task instead of
+    /// busy-polling.
+    ///
+    /// Mutually exclusive with the `blocking` feature's synchronous
+    /// `Writer::lock`; enable at most one of the two.
+    pub fn lock<'a>(&'a mut self) -> WriteLock<'a, 'b> {
+        WriteLock { writer: self }
+    }
+}
+
+/// The [`Future`](core::future::Future) returned by [`Writer::lock`].
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct WriteLock<'a, 'b> {
+    writer: &'a mut Writer<'b>,
+}
+
+#[cfg(feature = "async")]
+impl<'a, 'b> core::future::Future for WriteLock<'a, 'b> {
+    type Output = WriteGuard<'a, 'b>;
+
+    fn poll(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = core::pin::Pin::get_mut(self);
+
+        // SAFETY: see the identical cast in `ReadLock::poll`.
+        let writer = unsafe { &mut *(this.writer as *mut Writer<'b>) };
+
+        writer.poll_lock(cx)
+    }
+}
+
 #[derive(Debug)]
 pub struct WriteGuard<'a, 'b>
 where
@@ -272,6 +460,9 @@ where
     writer: Option<&'a mut Writer<'b>>,
     writing_to: Side,
     buffer: &'b mut [u8],
+
+    #[cfg(feature = "io")]
+    position: usize,
 }
 
 impl<'a, 'b> Deref for WriteGuard<'a, 'b> {
@@ -319,6 +510,11 @@ impl<'a, 'b> WriteGuard<'a, 'b> {
                 prev_readers = old;
             }
         }
+
+        #[cfg(feature = "async")]
+        if let Some(async_state) = writer.0.async_state {
+            async_state.reader_wakers.wake_all();
+        }
     }
 }
 
@@ -333,10 +529,282 @@ impl<'a, 'b> Drop for WriteGuard<'a, 'b> {
     }
 }
 
-#[cfg(test)]
+/// Tuning knobs for the blocking `lock()` retry loop.
+///
+/// The retry starts at a spin count of 1 and doubles it on every failed
+/// attempt, up to `cap`. Once the spin count reaches `yield_threshold`, the
+/// thread yields instead of spinning if the `std` feature is enabled;
+/// without `std`, it keeps spinning that many times per attempt.
+#[cfg(feature = "blocking")]
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    pub cap: u32,
+    pub yield_threshold: u32,
+}
+
+#[cfg(feature = "blocking")]
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            cap: 64,
+            yield_threshold: 64,
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Backoff {
+    fn step(&self, spins: &mut u32) {
+        if *spins >= self.yield_threshold {
+            #[cfg(feature = "std")]
+            std::thread::yield_now();
+
+            #[cfg(not(feature = "std"))]
+            for _ in 0..*spins {
+                core::hint::spin_loop();
+            }
+        } else {
+            for _ in 0..*spins {
+                core::hint::spin_loop();
+            }
+        }
+
+        *spins = (*spins * 2).min(self.cap);
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<'b> Reader<'b> {
+    /// Blocks until a read lock is acquired, retrying `try_lock` with
+    /// [`Backoff::default`] in between attempts.
+    ///
+    /// Mutually exclusive with the `async` feature's `Reader::lock`; enable
+    /// at most one of the two.
+    pub fn lock<'a>(&'a mut self) -> ReadGuard<'a, 'b> {
+        self.lock_with(Backoff::default())
+    }
+
+    /// Blocks until a read lock is acquired, retrying `try_lock` with a
+    /// caller-tuned [`Backoff`] in between attempts.
+    pub fn lock_with<'a>(&'a mut self, backoff: Backoff) -> ReadGuard<'a, 'b> {
+        let mut spins = 1;
+        let this: *mut Self = self;
+
+        loop {
+            // SAFETY: `this` is derived from the unique `&'a mut Self`
+            // passed in above; each iteration below reborrows it
+            // exclusively, and only one such reborrow is alive at a time.
+            match unsafe { &mut *this }.try_lock() {
+                Ok(guard) => return guard,
+                Err(()) => backoff.step(&mut spins),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<'b> Writer<'b> {
+    /// Blocks until a write lock is acquired, retrying `try_lock` with
+    /// [`Backoff::default`] in between attempts.
+    ///
+    /// Mutually exclusive with the `async` feature's `Writer::lock`; enable
+    /// at most one of the two.
+    pub fn lock<'a>(&'a mut self) -> WriteGuard<'a, 'b> {
+        self.lock_with(Backoff::default())
+    }
+
+    /// Blocks until a write lock is acquired, retrying `try_lock` with a
+    /// caller-tuned [`Backoff`] in between attempts.
+    pub fn lock_with<'a>(&'a mut self, backoff: Backoff) -> WriteGuard<'a, 'b> {
+        let mut spins = 1;
+        let this: *mut Self = self;
+
+        loop {
+            // SAFETY: `this` is derived from the unique `&'a mut Self`
+            // passed in above; each iteration below reborrows it
+            // exclusively, and only one such reborrow is alive at a time.
+            match unsafe { &mut *this }.try_lock() {
+                Ok(guard) => return guard,
+                Err(()) => backoff.step(&mut spins),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use super::*;
 
+    #[test]
+    fn take_writer_only_once() {
+        let owned = OwnedDoublet::new(1);
+
+        assert!(owned.take_writer().is_some());
+        assert!(owned.take_writer().is_none());
+    }
+
+    #[cfg(feature = "async")]
+    fn flag_waker(flag: &'static core::sync::atomic::AtomicBool) -> core::task::Waker {
+        use core::task::{RawWaker, RawWakerVTable, Waker};
+
+        unsafe fn clone(data: *const ()) -> RawWaker {
+            RawWaker::new(data, &VTABLE)
+        }
+        unsafe fn wake(data: *const ()) {
+            (*(data as *const core::sync::atomic::AtomicBool)).store(true, Ordering::SeqCst);
+        }
+        unsafe fn wake_by_ref(data: *const ()) {
+            wake(data);
+        }
+        unsafe fn drop(_data: *const ()) {}
+
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+        let raw = RawWaker::new(flag as *const _ as *const (), &VTABLE);
+        unsafe { Waker::from_raw(raw) }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn poll_lock_ready_when_uncontended() {
+        use core::task::{Context, Poll};
+
+        static WOKEN: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+
+        let owned = OwnedDoublet::new(1);
+        let mut writer = owned.take_writer().unwrap();
+        let mut reader = owned.reader();
+
+        let waker = flag_waker(&WOKEN);
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(matches!(writer.poll_lock(&mut cx), Poll::Ready(_)));
+        assert!(matches!(reader.poll_lock(&mut cx), Poll::Ready(_)));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn poll_lock_wakes_writer_once_stale_reader_drops() {
+        use core::sync::atomic::AtomicBool;
+        use core::task::{Context, Poll};
+
+        static WOKEN: AtomicBool = AtomicBool::new(false);
+
+        let owned = OwnedDoublet::new(1);
+        let mut writer = owned.take_writer().unwrap();
+        let mut reader = owned.reader();
+
+        // A reader picks up the initial (Left) side and holds it open.
+        let read_guard = reader.try_lock().unwrap();
+
+        // The first write cycle is uncontended (no prior cycle to drain).
+        {
+            let mut guard = writer.try_lock().unwrap();
+            guard[0] = 1;
+            guard.activate();
+        }
+
+        let waker = flag_waker(&WOKEN);
+        let mut cx = Context::from_waker(&waker);
+
+        // The second write claim has to wait for `read_guard`, which is
+        // still reading the now-stale Left side.
+        assert!(matches!(writer.poll_lock(&mut cx), Poll::Pending));
+        assert!(!WOKEN.load(Ordering::SeqCst));
+
+        core::mem::drop(read_guard);
+
+        assert!(WOKEN.load(Ordering::SeqCst));
+        assert!(matches!(writer.poll_lock(&mut cx), Poll::Ready(_)));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn backoff_caps_spin_count() {
+        let backoff = Backoff {
+            cap: 4,
+            yield_threshold: 1000,
+        };
+        let mut spins = 1;
+
+        backoff.step(&mut spins);
+        assert_eq!(2, spins);
+
+        backoff.step(&mut spins);
+        assert_eq!(4, spins);
+
+        backoff.step(&mut spins);
+        assert_eq!(4, spins);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn blocking_lock_returns_once_uncontended() {
+        let owned = OwnedDoublet::new(1);
+        let mut writer = owned.take_writer().unwrap();
+        let mut reader = owned.reader();
+
+        {
+            let mut guard = writer.lock();
+            guard[0] = 7;
+            guard.activate();
+        }
+
+        let guard = reader.lock();
+        assert_eq!(7, guard[0]);
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn read_guard_reads_and_tracks_position() {
+        use std::io::{BufRead, Read};
+
+        let owned = OwnedDoublet::new(4);
+
+        let mut writer = owned.take_writer().unwrap();
+        {
+            let mut guard = writer.try_lock().unwrap();
+            guard.copy_from_slice(&[1, 2, 3, 4]);
+            guard.activate();
+        }
+
+        let mut reader = owned.reader();
+        let mut guard = reader.try_lock().unwrap();
+
+        assert_eq!(0, guard.position());
+
+        let mut buf = [0u8; 2];
+        assert_eq!(2, guard.read(&mut buf).unwrap());
+        assert_eq!([1, 2], buf);
+        assert_eq!(2, guard.position());
+
+        // Reading past the end of the buffer is a short read, not an error.
+        let mut buf = [0u8; 4];
+        assert_eq!(2, guard.read(&mut buf).unwrap());
+        assert_eq!([3, 4, 0, 0], buf);
+        assert_eq!(0, guard.read(&mut buf).unwrap());
+
+        assert!(guard.fill_buf().unwrap().is_empty());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn write_guard_rewinds() {
+        use std::io::Write;
+
+        let owned = OwnedDoublet::new(4);
+        let mut writer = owned.take_writer().unwrap();
+        let mut guard = writer.try_lock().unwrap();
+
+        // A write past the end of the buffer is a short write, not an error.
+        assert_eq!(4, guard.write(&[9, 9, 9, 9, 9]).unwrap());
+        assert_eq!([9, 9, 9, 9], &guard[..]);
+
+        guard.rewind();
+        assert_eq!(2, guard.write(&[1, 2]).unwrap());
+        assert_eq!([1, 2, 9, 9], &guard[..]);
+    }
+
     #[test]
     fn lkwr_unwr_lkrd_unrd() {
         let owned = OwnedDoublet::new(1);
@@ -416,7 +884,7 @@ mod tests {
         assert_eq!(state, owned.header.toggle.load(Ordering::SeqCst));
         assert_eq!(1, owned.header.remaining_readers.load(Ordering::SeqCst));
 
-        ::std::mem::drop(rd_guard);
+        core::mem::drop(rd_guard);
 
         assert_eq!(0, owned.header.remaining_readers.load(Ordering::SeqCst));
     }
@@ -450,7 +918,7 @@ mod tests {
         wr_guard[0] = 55;
 
         // Drop the read guard
-        ::std::mem::drop(rd_guard);
+        core::mem::drop(rd_guard);
 
         let state = State {
             side: Side::Left,
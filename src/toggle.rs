@@ -1,5 +1,5 @@
-use std::ops::Not;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use core::ops::Not;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Side {
@@ -0,0 +1,128 @@
+//! Waker storage backing the poll-based locking API.
+//!
+//! This is deliberately minimal: a spinlock guarding a single slot
+//! ([`AtomicWaker`]) for the lone writer, and a spinlock guarding a `Vec`
+//! ([`WakerSet`]) for the unbounded set of readers. Both rely only on
+//! `core`, but `WakerSet` stores its wakers in a `Vec`, so the `async`
+//! feature requires `alloc`.
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+
+use alloc::vec::Vec;
+
+fn spin_lock(locked: &AtomicBool) {
+    while locked
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        core::hint::spin_loop();
+    }
+}
+
+/// A single waker slot, shared by the one writer allowed on a doublet.
+#[derive(Default)]
+pub(crate) struct AtomicWaker {
+    locked: AtomicBool,
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+unsafe impl Send for AtomicWaker {}
+unsafe impl Sync for AtomicWaker {}
+
+impl fmt::Debug for AtomicWaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AtomicWaker").finish()
+    }
+}
+
+impl AtomicWaker {
+    pub(crate) fn register(&self, waker: &Waker) {
+        spin_lock(&self.locked);
+
+        unsafe {
+            *self.waker.get() = Some(waker.clone());
+        }
+
+        self.locked.store(false, Ordering::Release);
+    }
+
+    pub(crate) fn wake(&self) {
+        spin_lock(&self.locked);
+
+        let waker = unsafe { (*self.waker.get()).take() };
+
+        self.locked.store(false, Ordering::Release);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// The set of wakers registered by readers waiting on a write to land.
+#[derive(Default)]
+pub(crate) struct WakerSet {
+    locked: AtomicBool,
+    wakers: UnsafeCell<Vec<Waker>>,
+}
+
+unsafe impl Send for WakerSet {}
+unsafe impl Sync for WakerSet {}
+
+impl fmt::Debug for WakerSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WakerSet").finish()
+    }
+}
+
+impl WakerSet {
+    pub(crate) fn register(&self, waker: &Waker) {
+        spin_lock(&self.locked);
+
+        let wakers = unsafe { &mut *self.wakers.get() };
+
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+
+        self.locked.store(false, Ordering::Release);
+    }
+
+    pub(crate) fn wake_all(&self) {
+        spin_lock(&self.locked);
+
+        let drained = core::mem::take(unsafe { &mut *self.wakers.get() });
+
+        self.locked.store(false, Ordering::Release);
+
+        for waker in drained {
+            waker.wake();
+        }
+    }
+}
+
+/// The waker registrations shared by a single doublet's readers and writer.
+///
+/// This is process-local state: `Waker`s carry a vtable pointer into this
+/// process's code, and `WakerSet` backs its storage with a `Vec`, i.e. a
+/// heap pointer into this process's allocator. Neither survives being
+/// copied into another address space, so this must never live inside
+/// [`Header`](crate::Header) (which `Doublet::from_raw_parts` maps directly
+/// onto caller-provided, possibly shared or cross-process, memory). It's
+/// instead owned by [`OwnedDoublet`](crate::OwnedDoublet) and reached
+/// through a plain `&'a` reference, the same way non-`repr(C)` crate state
+/// always is.
+#[derive(Default)]
+pub(crate) struct AsyncState {
+    pub(crate) writer_waker: AtomicWaker,
+    pub(crate) reader_wakers: WakerSet,
+}
+
+impl fmt::Debug for AsyncState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncState").finish()
+    }
+}